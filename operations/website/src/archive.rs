@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::body::Body;
+use bytes::Bytes;
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const CENTRAL_DIR_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const ZIP64_EOCD_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x0606_4b50;
+const ZIP64_EOCD_LOCATOR_SIZE: u64 = 20;
+const ZIP64_EOCD_RECORD_SIZE: u64 = 56;
+const ZIP64_EXTRA_FIELD_TAG: u16 = 0x0001;
+const EOCD_FIXED_SIZE: u64 = 22;
+const MAX_COMMENT_SIZE: u64 = 65_535;
+const READ_CHUNK_SIZE: usize = 65_536;
+
+/// Location and encoding of a single entry within a release ZIP.
+#[derive(Clone, Copy)]
+pub struct Entry {
+    pub local_header_offset: u64,
+    pub compression_method: u16,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+}
+
+/// Maps entry names to their position in a release ZIP's central directory.
+///
+/// Keys are the full internal ZIP path (e.g. `stories/12345/story.html`),
+/// including any directory prefix. Callers that only have a bare story id
+/// should go through [`resolve`] rather than indexing directly.
+pub type Index = HashMap<String, Entry>;
+
+/// Resolves a request id to an entry, without requiring the caller to know
+/// the release's internal directory layout.
+///
+/// Tries an exact match on the full ZIP path first, then falls back to a
+/// match on entry basename (the final `/`-separated path component). A
+/// basename match is only honored if it's unique; an id that's ambiguous
+/// between two entries is treated as not found rather than guessed at.
+pub fn resolve<'a>(index: &'a Index, id: &str) -> Option<&'a Entry> {
+    if let Some(entry) = index.get(id) {
+        return Some(entry);
+    }
+
+    let mut matches = index
+        .iter()
+        .filter(|(name, _)| basename(name) == id)
+        .map(|(_, entry)| entry);
+
+    let entry = matches.next()?;
+
+    if matches.next().is_some() {
+        return None;
+    }
+
+    Some(entry)
+}
+
+fn basename(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+/// Builds an [`Index`] by reading the central directory of a ZIP file.
+///
+/// Scans backwards from EOF for the end-of-central-directory record, then
+/// walks the central directory it points to. This avoids unpacking the
+/// archive just to locate a single entry.
+pub fn build_index(path: &Path) -> io::Result<Index> {
+    let mut file = File::open(path)?;
+    let (cd_offset, cd_size) = find_central_directory(&mut file)?;
+
+    file.seek(SeekFrom::Start(cd_offset))?;
+    let mut reader = io::BufReader::new(file);
+    let mut remaining = cd_size;
+    let mut index = Index::new();
+
+    while remaining >= 46 {
+        let signature = read_u32(&mut reader)?;
+        if signature != CENTRAL_DIR_SIGNATURE {
+            break;
+        }
+
+        let mut header = [0u8; 42];
+        reader.read_exact(&mut header)?;
+
+        let compression_method = u16::from_le_bytes([header[6], header[7]]);
+        let mut compressed_size =
+            u32::from_le_bytes([header[16], header[17], header[18], header[19]]) as u64;
+        let mut uncompressed_size =
+            u32::from_le_bytes([header[20], header[21], header[22], header[23]]) as u64;
+        let name_len = u16::from_le_bytes([header[24], header[25]]) as usize;
+        let extra_len = u16::from_le_bytes([header[26], header[27]]) as usize;
+        let comment_len = u16::from_le_bytes([header[28], header[29]]) as usize;
+        let mut local_header_offset =
+            u32::from_le_bytes([header[38], header[39], header[40], header[41]]) as u64;
+
+        let mut name = vec![0u8; name_len];
+        reader.read_exact(&mut name)?;
+
+        let mut extra = vec![0u8; extra_len];
+        reader.read_exact(&mut extra)?;
+        io::copy(&mut (&mut reader).take(comment_len as u64), &mut io::sink())?;
+
+        apply_zip64_extra(
+            &extra,
+            &mut uncompressed_size,
+            &mut compressed_size,
+            &mut local_header_offset,
+        )?;
+
+        let name = String::from_utf8_lossy(&name).into_owned();
+        index.insert(
+            name,
+            Entry {
+                local_header_offset,
+                compression_method,
+                compressed_size,
+                uncompressed_size,
+            },
+        );
+
+        let consumed = (46 + name_len + extra_len + comment_len) as u64;
+        remaining = remaining.checked_sub(consumed).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "central directory entry overruns its record",
+            )
+        })?;
+    }
+
+    Ok(index)
+}
+
+/// Scans backwards from EOF for the end-of-central-directory signature and
+/// returns the `(offset, size)` of the central directory it describes.
+///
+/// Fimfarchive's release ZIPs run into the tens of GB, so the classic
+/// 32-bit fields routinely overflow and are reported as `0xFFFFFFFF`; in
+/// that case the real offset/size are read from the ZIP64
+/// end-of-central-directory locator and record that precede the classic
+/// EOCD (APPNOTE 4.5.3).
+fn find_central_directory(file: &mut File) -> io::Result<(u64, u64)> {
+    let file_len = file.seek(SeekFrom::End(0))?;
+    let scan_len = EOCD_FIXED_SIZE
+        .saturating_add(MAX_COMMENT_SIZE)
+        .min(file_len);
+
+    file.seek(SeekFrom::End(-(scan_len as i64)))?;
+    let mut buf = vec![0u8; scan_len as usize];
+    file.read_exact(&mut buf)?;
+
+    let signature = EOCD_SIGNATURE.to_le_bytes();
+    let found = buf
+        .windows(4)
+        .enumerate()
+        .rev()
+        .filter(|(_, window)| *window == signature)
+        .map(|(pos, _)| pos)
+        .find(|&pos| eocd_comment_length_matches(&buf, pos))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no end-of-central-directory record",
+            )
+        })?;
+
+    let eocd = &buf[found..];
+    let cd_size = u32::from_le_bytes([eocd[12], eocd[13], eocd[14], eocd[15]]);
+    let cd_offset = u32::from_le_bytes([eocd[16], eocd[17], eocd[18], eocd[19]]);
+
+    if cd_size != u32::MAX && cd_offset != u32::MAX {
+        return Ok((cd_offset as u64, cd_size as u64));
+    }
+
+    let eocd_offset = file_len - scan_len + found as u64;
+    find_zip64_central_directory(file, eocd_offset)
+}
+
+/// Checks that a candidate EOCD signature at `pos` is a real record rather
+/// than four bytes that happen to match inside an earlier record's comment:
+/// the comment-length field it declares must account for exactly the rest
+/// of `buf`, since the EOCD is always the last thing in the file.
+fn eocd_comment_length_matches(buf: &[u8], pos: usize) -> bool {
+    let fixed_size = EOCD_FIXED_SIZE as usize;
+    let Some(eocd) = buf.get(pos..pos + fixed_size) else {
+        return false;
+    };
+
+    let comment_len = u16::from_le_bytes([eocd[20], eocd[21]]) as usize;
+    pos + fixed_size + comment_len == buf.len()
+}
+
+/// Reads the ZIP64 end-of-central-directory locator and record that sit
+/// immediately before the classic EOCD, resolving the real 64-bit central
+/// directory offset and size.
+fn find_zip64_central_directory(file: &mut File, eocd_offset: u64) -> io::Result<(u64, u64)> {
+    let locator_offset = eocd_offset
+        .checked_sub(ZIP64_EOCD_LOCATOR_SIZE)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated zip64 end-of-central-directory locator",
+            )
+        })?;
+
+    file.seek(SeekFrom::Start(locator_offset))?;
+    let mut locator = [0u8; ZIP64_EOCD_LOCATOR_SIZE as usize];
+    file.read_exact(&mut locator)?;
+
+    let signature = u32::from_le_bytes([locator[0], locator[1], locator[2], locator[3]]);
+    if signature != ZIP64_EOCD_LOCATOR_SIGNATURE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing zip64 end-of-central-directory locator",
+        ));
+    }
+
+    let record_offset = u64::from_le_bytes(locator[8..16].try_into().unwrap());
+
+    file.seek(SeekFrom::Start(record_offset))?;
+    let mut record = [0u8; ZIP64_EOCD_RECORD_SIZE as usize];
+    file.read_exact(&mut record)?;
+
+    let signature = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+    if signature != ZIP64_EOCD_SIGNATURE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing zip64 end-of-central-directory record",
+        ));
+    }
+
+    let cd_size = u64::from_le_bytes(record[40..48].try_into().unwrap());
+    let cd_offset = u64::from_le_bytes(record[48..56].try_into().unwrap());
+
+    Ok((cd_offset, cd_size))
+}
+
+/// Overrides 32-bit-sentinel central-directory fields with their 64-bit
+/// values from the ZIP64 extended information extra field (tag `0x0001`).
+///
+/// Per APPNOTE 4.5.3, only the fields that were `0xFFFFFFFF` in the fixed
+/// header are present, always in this order: uncompressed size,
+/// compressed size, local header offset, disk number.
+fn apply_zip64_extra(
+    extra: &[u8],
+    uncompressed_size: &mut u64,
+    compressed_size: &mut u64,
+    local_header_offset: &mut u64,
+) -> io::Result<()> {
+    let mut cursor = extra;
+
+    while cursor.len() >= 4 {
+        let tag = u16::from_le_bytes([cursor[0], cursor[1]]);
+        let size = u16::from_le_bytes([cursor[2], cursor[3]]) as usize;
+        let Some(data) = cursor.get(4..4 + size) else {
+            break;
+        };
+
+        if tag == ZIP64_EXTRA_FIELD_TAG {
+            let mut fields = data;
+
+            if *uncompressed_size == u64::from(u32::MAX) {
+                *uncompressed_size = take_u64(&mut fields)?;
+            }
+            if *compressed_size == u64::from(u32::MAX) {
+                *compressed_size = take_u64(&mut fields)?;
+            }
+            if *local_header_offset == u64::from(u32::MAX) {
+                *local_header_offset = take_u64(&mut fields)?;
+            }
+
+            return Ok(());
+        }
+
+        cursor = &cursor[4 + size..];
+    }
+
+    Ok(())
+}
+
+fn take_u64(fields: &mut &[u8]) -> io::Result<u64> {
+    let Some(bytes) = fields.get(..8) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "truncated zip64 extra field",
+        ));
+    };
+    *fields = &fields[8..];
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Opens the local file header for `entry` and returns a reader positioned
+/// at the start of its (still compressed) data.
+fn open_entry_data(path: &Path, entry: &Entry) -> io::Result<Box<dyn Read + Send>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(entry.local_header_offset))?;
+
+    let mut header = [0u8; 30];
+    file.read_exact(&mut header)?;
+
+    let signature = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    if signature != LOCAL_HEADER_SIGNATURE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "bad local file header",
+        ));
+    }
+
+    let name_len = u16::from_le_bytes([header[26], header[27]]) as i64;
+    let extra_len = u16::from_le_bytes([header[28], header[29]]) as i64;
+    file.seek(SeekFrom::Current(name_len + extra_len))?;
+
+    let compressed = file.take(entry.compressed_size);
+
+    Ok(match entry.compression_method {
+        0 => Box::new(compressed),
+        8 => Box::new(flate2::read::DeflateDecoder::new(compressed)),
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported compression method {other}"),
+            ))
+        }
+    })
+}
+
+/// Streams a single ZIP entry's decompressed bytes as a response body.
+///
+/// Runs the actual read/inflate loop on a blocking task and forwards
+/// chunks over a channel, so the Tokio runtime isn't blocked on file I/O.
+pub fn stream_entry(path: Arc<Path>, entry: Entry) -> io::Result<Body> {
+    let mut reader = open_entry_data(&path, &entry)?;
+    let (tx, rx) = tokio::sync::mpsc::channel::<io::Result<Bytes>>(4);
+
+    tokio::task::spawn_blocking(move || {
+        let mut buf = vec![0u8; READ_CHUNK_SIZE];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if tx
+                        .blocking_send(Ok(Bytes::copy_from_slice(&buf[..n])))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    let _ = tx.blocking_send(Err(err));
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(Body::from_stream(
+        tokio_stream::wrappers::ReceiverStream::new(rx),
+    ))
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use axum::extract::FromRequestParts;
+use axum::http::{header, request::Parts, StatusCode};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use subtle::ConstantTimeEq;
+
+use crate::AppState;
+
+/// Extractor that rejects requests unless they carry the configured
+/// bearer or basic-auth credential, gating write and compute-heavy routes
+/// without needing a reverse proxy in front of them.
+pub struct RequireToken;
+
+impl FromRequestParts<Arc<AppState>> for RequireToken {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(expected) = &state.access_token else {
+            return Ok(RequireToken);
+        };
+
+        let header_value = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+
+        let credential = extract_credential(header_value).ok_or(StatusCode::UNAUTHORIZED)?;
+
+        if credential.ct_ne(expected.as_bytes()).into() {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+
+        Ok(RequireToken)
+    }
+}
+
+/// Pulls the bearer token or basic-auth password out of an `Authorization`
+/// header value.
+///
+/// Basic credentials are `base64("user:password")`; the username is
+/// ignored and the password is compared against `access_token`, since this
+/// extractor gates a single shared credential rather than per-user ones.
+fn extract_credential(header_value: &str) -> Option<Vec<u8>> {
+    if let Some(token) = header_value.strip_prefix("Bearer ") {
+        return Some(token.as_bytes().to_vec());
+    }
+
+    let encoded = header_value.strip_prefix("Basic ")?;
+    let decoded = BASE64.decode(encoded).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (_user, password) = decoded.split_once(':')?;
+
+    Some(password.as_bytes().to_vec())
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+const DEFAULT_BIND: &str = "[::]:34407";
+const DEFAULT_RELEASES_DIR: &str = "releases";
+const DEFAULT_BUF_CHUNK_SIZE: usize = 16_777_216;
+const DEFAULT_FALLBACK_URL: &str = "https://www.fimfiction.net/user/116950/Fimfarchive";
+
+#[derive(Parser)]
+#[command(name = "fimfarchive-website", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the HTTP server.
+    Serve(ServeArgs),
+}
+
+#[derive(Parser)]
+pub struct ServeArgs {
+    /// Address to listen on.
+    #[arg(long, env = "BIND", default_value = DEFAULT_BIND)]
+    pub bind: String,
+
+    /// Directory holding release archives.
+    #[arg(long, env = "RELEASES_DIR", default_value = DEFAULT_RELEASES_DIR)]
+    pub releases_dir: PathBuf,
+
+    /// Buffer size used when serving release files.
+    #[arg(long, env = "BUF_CHUNK_SIZE", default_value_t = DEFAULT_BUF_CHUNK_SIZE)]
+    pub buf_chunk_size: usize,
+
+    /// Redirect target for unmatched routes.
+    #[arg(long, env = "FALLBACK_URL", default_value = DEFAULT_FALLBACK_URL)]
+    pub fallback_url: String,
+
+    /// Bearer/basic-auth credential required for write and extraction
+    /// routes. Left unset, those routes are open.
+    #[arg(long, env = "ACCESS_TOKEN")]
+    pub access_token: Option<String>,
+}
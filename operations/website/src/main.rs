@@ -1,26 +1,191 @@
-use axum::Router;
-use axum::response::Redirect;
-use std::env::var;
+mod archive;
+mod auth;
+mod cli;
+mod manifest;
+mod upload;
+
+use std::collections::HashMap;
 use std::io::Result;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use clap::Parser;
 use tokio::net::TcpListener;
+use tower::limit::ConcurrencyLimitLayer;
+use tower_governor::governor::GovernorConfigBuilder;
+use tower_governor::GovernorLayer;
 use tower_http::services::ServeDir;
 
-const BIND: &str = "[::]:34407";
-const CHUNK: usize = 16_777_216;
-const PROFILE: &str = "https://www.fimfiction.net/user/116950/Fimfarchive";
+use archive::Index;
+use auth::RequireToken;
+use cli::{Cli, Command, ServeArgs};
+use manifest::DigestCache;
+
+/// Maximum number of simultaneous in-flight requests per heavy route: the
+/// extraction and upload routes each get their own limit of this size.
+const MAX_CONCURRENT_HEAVY_REQUESTS: usize = 16;
+/// Sustained and burst rate limit applied to extraction/upload requests,
+/// keyed per client IP (`ConcurrencyLimitLayer` shares an
+/// `Arc<Semaphore>`, but `tower::limit::RateLimitLayer`'s quota lives in
+/// per-clone state and axum clones the service per request, so it would
+/// silently reset every time; `tower_governor` keeps its quota behind a
+/// shared keyed store instead).
+const HEAVY_REQUESTS_PER_SECOND: u64 = 64;
+const HEAVY_REQUEST_BURST_SIZE: u32 = 64;
+
+/// Shared state handed to every route via [`axum::extract::State`].
+pub(crate) struct AppState {
+    pub(crate) releases_dir: PathBuf,
+    /// Staging directory for in-progress uploads, kept outside
+    /// `releases_dir` so a partially written upload is never served.
+    pub(crate) incoming_dir: PathBuf,
+    pub(crate) buf_chunk_size: usize,
+    fallback_url: String,
+    pub(crate) access_token: Option<String>,
+    zip_index_cache: Mutex<HashMap<String, Arc<Index>>>,
+    pub(crate) digest_cache: Arc<DigestCache>,
+}
+
+fn releases(state: &AppState) -> ServeDir {
+    ServeDir::new(&state.releases_dir).with_buf_chunk_size(state.buf_chunk_size)
+}
+
+/// Sibling directory used to stage uploads before they're renamed into
+/// `releases_dir`, e.g. `releases` → `.releases.incoming`.
+fn incoming_dir_for(releases_dir: &std::path::Path) -> PathBuf {
+    let name = releases_dir
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    releases_dir.with_file_name(format!(".{name}.incoming"))
+}
+
+/// Looks up (and lazily builds) the ZIP central-directory index for a release.
+async fn load_index(state: &AppState, release: &str) -> Result<Arc<Index>> {
+    if let Some(index) = state.zip_index_cache.lock().unwrap().get(release) {
+        return Ok(index.clone());
+    }
+
+    let path = state.releases_dir.join(release);
+    let index = Arc::new(
+        tokio::task::spawn_blocking(move || archive::build_index(&path))
+            .await
+            .map_err(std::io::Error::other)??,
+    );
+
+    state
+        .zip_index_cache
+        .lock()
+        .unwrap()
+        .insert(release.to_owned(), index.clone());
 
-fn releases() -> ServeDir {
-    ServeDir::new("releases").with_buf_chunk_size(CHUNK)
+    Ok(index)
+}
+
+/// `GET /releases/:release/story/:id` — streams a single archive entry
+/// without requiring the client to download the whole release.
+///
+/// Gated by [`RequireToken`] and rate-limited alongside uploads: inflating
+/// many entries concurrently is CPU-bound, so this can't be left as open
+/// as the static `/releases` downloads.
+///
+/// `id` is resolved via [`archive::resolve`], so callers can address an
+/// entry by its basename without knowing the release's internal directory
+/// prefix.
+async fn story(
+    State(state): State<Arc<AppState>>,
+    _token: RequireToken,
+    Path((release, id)): Path<(String, String)>,
+) -> Response {
+    let Ok(index) = load_index(&state, &release).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let Some(entry) = archive::resolve(&index, &id).copied() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let path: Arc<std::path::Path> = state.releases_dir.join(&release).into();
+    match archive::stream_entry(path, entry) {
+        Ok(body) => (
+            [(
+                axum::http::header::CONTENT_LENGTH,
+                entry.uncompressed_size.to_string(),
+            )],
+            body,
+        )
+            .into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let address = var("BIND").unwrap_or(BIND.into());
-    let listener = TcpListener::bind(address).await?;
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Serve(args) => serve(args).await,
+    }
+}
+
+async fn serve(args: ServeArgs) -> Result<()> {
+    let listener = TcpListener::bind(&args.bind).await?;
+
+    let incoming_dir = incoming_dir_for(&args.releases_dir);
+    tokio::fs::create_dir_all(&incoming_dir).await?;
+
+    let state = Arc::new(AppState {
+        releases_dir: args.releases_dir,
+        incoming_dir,
+        buf_chunk_size: args.buf_chunk_size,
+        fallback_url: args.fallback_url,
+        access_token: args.access_token,
+        zip_index_cache: Mutex::new(HashMap::new()),
+        digest_cache: Arc::new(DigestCache::default()),
+    });
+
+    let release_service = releases(&state);
+
+    let governor_config = Arc::new(
+        GovernorConfigBuilder::default()
+            .per_nanosecond(1_000_000_000 / HEAVY_REQUESTS_PER_SECOND)
+            .burst_size(HEAVY_REQUEST_BURST_SIZE)
+            .finish()
+            .expect("valid governor configuration"),
+    );
+
+    let heavy_routes = Router::new()
+        .route("/:release/story/:id", get(story))
+        .route("/", post(upload::publish))
+        .layer(ConcurrencyLimitLayer::new(MAX_CONCURRENT_HEAVY_REQUESTS))
+        .layer(GovernorLayer {
+            config: governor_config,
+        });
+
+    // `heavy_routes`' dynamic `:release` segment and the static-file
+    // fallback must not both be registered as routes on the same router:
+    // axum's matcher rejects a named param and a wildcard capture at the
+    // same path position. Using `fallback_service` for the static files
+    // sidesteps that — it's consulted only when nothing above matches,
+    // rather than being inserted into the route table.
+    let releases_routes = Router::new()
+        .route("/manifest.json", get(manifest::manifest))
+        .merge(heavy_routes)
+        .fallback_service(release_service)
+        .with_state(state.clone());
 
     let routes = Router::new()
-        .nest_service("/releases", releases())
-        .fallback(async || Redirect::to(PROFILE));
+        .nest("/releases", releases_routes)
+        .fallback(move || {
+            let fallback_url = state.fallback_url.clone();
+            async move { Redirect::to(&fallback_url) }
+        });
+    let routes = routes.into_make_service_with_connect_info::<std::net::SocketAddr>();
 
     axum::serve(listener, routes).await
 }
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use axum::extract::State;
+use axum::Json;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::AppState;
+
+/// Key under which a previously computed digest is cached: a file is
+/// re-hashed only if its size or modification time has changed.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct DigestCacheKey {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// Caches SHA-256 digests keyed by path, size and modification time, so
+/// multi-gigabyte release files aren't re-hashed on every request.
+#[derive(Default)]
+pub struct DigestCache {
+    digests: Mutex<HashMap<DigestCacheKey, String>>,
+}
+
+#[derive(Serialize)]
+pub struct ReleaseManifest {
+    name: String,
+    size: u64,
+    modified: u64,
+    sha256: String,
+}
+
+/// `GET /releases/manifest.json` — lists every release with its size,
+/// modification time and SHA-256 digest, so mirrors can discover and
+/// verify releases without downloading them first.
+pub async fn manifest(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ReleaseManifest>>, axum::http::StatusCode> {
+    let releases_dir = state.releases_dir.clone();
+    let digest_cache = state.digest_cache.clone();
+    let buf_chunk_size = state.buf_chunk_size;
+
+    tokio::task::spawn_blocking(move || {
+        build_manifest(&releases_dir, &digest_cache, buf_chunk_size)
+    })
+    .await
+    .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?
+    .map(Json)
+    .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn build_manifest(
+    releases_dir: &Path,
+    digest_cache: &DigestCache,
+    buf_chunk_size: usize,
+) -> io::Result<Vec<ReleaseManifest>> {
+    let mut manifest = Vec::new();
+
+    for entry in std::fs::read_dir(releases_dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let metadata = entry.metadata()?;
+        let modified = metadata.modified()?;
+        let key = DigestCacheKey {
+            path: entry.path(),
+            size: metadata.len(),
+            modified,
+        };
+
+        let sha256 = digest_for(digest_cache, key, &entry.path(), buf_chunk_size)?;
+
+        manifest.push(ReleaseManifest {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            size: metadata.len(),
+            modified: modified
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            sha256,
+        });
+    }
+
+    manifest.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(manifest)
+}
+
+fn digest_for(
+    cache: &DigestCache,
+    key: DigestCacheKey,
+    path: &Path,
+    buf_chunk_size: usize,
+) -> io::Result<String> {
+    if let Some(digest) = cache.digests.lock().unwrap().get(&key) {
+        return Ok(digest.clone());
+    }
+
+    let digest = hash_file(path, buf_chunk_size)?;
+    cache.digests.lock().unwrap().insert(key, digest.clone());
+
+    Ok(digest)
+}
+
+fn hash_file(path: &Path, buf_chunk_size: usize) -> io::Result<String> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; buf_chunk_size];
+
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
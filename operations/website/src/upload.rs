@@ -0,0 +1,156 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use axum::body::BodyDataStream;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use flate2::read::GzDecoder;
+use futures_util::TryStreamExt;
+use tar::Archive;
+use tokio::fs::File;
+use tokio::io::BufWriter;
+use tokio_util::io::StreamReader;
+use uuid::Uuid;
+
+use crate::auth::RequireToken;
+use crate::AppState;
+
+const RELEASE_NAME_HEADER: &str = "x-release-name";
+
+/// `POST /releases` — accepts a new release archive as a streaming upload.
+///
+/// Gated by [`RequireToken`]: publishing is a write operation and must not
+/// be open to anonymous clients the way static `/releases` downloads are.
+/// The caller names the release via the `X-Release-Name` header; the body
+/// is copied straight to a temporary file under [`AppState::incoming_dir`]
+/// (a sibling of `releases_dir`, so a partial upload is never served) so
+/// memory stays flat even for multi-gigabyte archives. Once the upload
+/// completes, the archive is validated on a blocking task and, on success,
+/// staged intact and atomically renamed into `releases_dir` — it is never
+/// unpacked, since [`crate::archive`] seeks into the whole archive file
+/// later and [`crate::manifest`] hashes it.
+pub async fn publish(
+    State(state): State<std::sync::Arc<AppState>>,
+    _token: RequireToken,
+    headers: HeaderMap,
+    body: BodyDataStream,
+) -> StatusCode {
+    let Some(name) = headers
+        .get(RELEASE_NAME_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+    else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    let temp_path = state.incoming_dir.join(Uuid::new_v4().to_string());
+
+    if receive(body, &temp_path).await.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        return StatusCode::BAD_REQUEST;
+    }
+
+    let final_path = match tokio::task::spawn_blocking({
+        let temp_path = temp_path.clone();
+        let releases_dir = state.releases_dir.clone();
+        move || validate_and_stage(&temp_path, &releases_dir, &name)
+    })
+    .await
+    {
+        Ok(Ok(path)) => path,
+        _ => {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return StatusCode::UNPROCESSABLE_ENTITY;
+        }
+    };
+
+    match tokio::fs::rename(&temp_path, &final_path).await {
+        Ok(()) => StatusCode::CREATED,
+        Err(_) => {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Copies the request body into `temp_path` without buffering it in memory.
+async fn receive(body: BodyDataStream, temp_path: &Path) -> io::Result<()> {
+    let stream = body.map_err(io::Error::other);
+    let mut reader = StreamReader::new(stream);
+    let mut writer = BufWriter::new(File::create(temp_path).await?);
+
+    tokio::io::copy(&mut reader, &mut writer).await?;
+    tokio::io::AsyncWriteExt::flush(&mut writer).await?;
+
+    Ok(())
+}
+
+/// Validates that `temp_path` is a well-formed release archive — a ZIP or
+/// a gzipped tarball, the two formats Fimfarchive releases ship as — and
+/// returns the path it should be staged at. The archive itself is left
+/// untouched; only its structure is read through.
+///
+/// Runs on a blocking task since both the ZIP central-directory scan and
+/// the gzip/tar read are synchronous, CPU-bound operations.
+fn validate_and_stage(temp_path: &Path, releases_dir: &Path, name: &str) -> io::Result<PathBuf> {
+    let name = sanitize_release_name(name)?;
+
+    if name.ends_with(".zip") {
+        let index = crate::archive::build_index(temp_path)?;
+
+        if index.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "archive has no entries",
+            ));
+        }
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        validate_tar_gz(temp_path)?;
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unsupported release archive extension",
+        ));
+    }
+
+    Ok(releases_dir.join(name))
+}
+
+/// Walks every tar entry through the gzip decoder to confirm the archive
+/// is well-formed, without writing anything back to disk.
+fn validate_tar_gz(path: &Path) -> io::Result<()> {
+    let file = std::fs::File::open(path)?;
+    let mut archive = Archive::new(GzDecoder::new(file));
+    let mut saw_entry = false;
+
+    for entry in archive.entries()? {
+        entry?;
+        saw_entry = true;
+    }
+
+    if !saw_entry {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "archive has no entries",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Rejects release names that could escape `releases_dir` via path
+/// traversal. The archive format is not checked here; `validate_and_stage`
+/// checks the extension.
+fn sanitize_release_name(name: &str) -> io::Result<String> {
+    let traversal =
+        name.is_empty() || name.contains('/') || name.contains('\\') || name == "." || name == "..";
+
+    if traversal {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid release name",
+        ));
+    }
+
+    Ok(name.to_owned())
+}